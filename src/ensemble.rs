@@ -0,0 +1,858 @@
+use std::collections::HashMap;
+
+use crate::math::Uint128;
+use crate::types::{
+    BlockInfo, Coin, ContractInfo, ContractResult, CosmosMsg, DistributionAction, DistributionMsg,
+    IbcAction, IbcMsg, MessageInfo, Params, Reply, Response, Result as ContractResultInner,
+    ReplyOn, StakingAction, StakingMsg, SubMsgResponse, SubMsgResult,
+};
+
+// handles a single contract's entry point, the same signature a real wasm contract exports
+pub trait ContractHandler {
+    fn handle(&self, params: Params, msg: String) -> ContractResult;
+
+    // contracts that never send a submessage with a reply_on set don't need to implement
+    // this; it's only invoked for messages that asked to be replied to
+    fn reply(&self, _params: Params, _reply: Reply) -> ContractResult {
+        ContractResult {
+            res: Some(ContractResultInner::Err(
+                "contract does not implement reply".to_string(),
+            )),
+        }
+    }
+}
+
+// a bank keeps per-address balances, like the sdk bank module the real chain runs
+#[derive(Default, Clone)]
+struct Bank {
+    balances: HashMap<String, Vec<Coin>>,
+}
+
+impl Bank {
+    fn balance_of(&self, addr: &str) -> Vec<Coin> {
+        self.balances.get(addr).cloned().unwrap_or_default()
+    }
+
+    fn amount_of(&self, addr: &str, denom: &str) -> Uint128 {
+        self.balance_of(addr)
+            .iter()
+            .find(|c| c.denom == denom)
+            .map(|c| c.amount())
+            .unwrap_or_default()
+    }
+
+    fn set_amount(&mut self, addr: &str, denom: &str, amount: Uint128) {
+        let entry = self.balances.entry(addr.to_string()).or_default();
+        if let Some(coin) = entry.iter_mut().find(|c| c.denom == denom) {
+            coin.amount = amount.to_string();
+        } else {
+            entry.push(Coin::new(amount, denom));
+        }
+    }
+
+    fn add(&mut self, addr: &str, amount: &[Coin]) -> Result<(), String> {
+        for coin in amount {
+            let current = self.amount_of(addr, &coin.denom);
+            let updated = current.checked_add(coin.amount()).map_err(|_| {
+                format!(
+                    "balance overflow: crediting {}{} to {} on top of {}",
+                    coin.amount(), coin.denom, addr, current
+                )
+            })?;
+            self.set_amount(addr, &coin.denom, updated);
+        }
+        Ok(())
+    }
+
+    fn subtract(&mut self, addr: &str, amount: &[Coin]) -> Result<(), String> {
+        for coin in amount {
+            let current = self.amount_of(addr, &coin.denom);
+            let sent = coin.amount();
+            let remaining = current.checked_sub(sent).map_err(|_| {
+                format!(
+                    "insufficient funds: {} has {}{}, needs {}{}",
+                    addr, current, coin.denom, sent, coin.denom
+                )
+            })?;
+            self.set_amount(addr, &coin.denom, remaining);
+        }
+        Ok(())
+    }
+
+    fn transfer(&mut self, from: &str, to: &str, amount: &[Coin]) -> Result<(), String> {
+        self.subtract(from, amount)?;
+        self.add(to, amount)?;
+        Ok(())
+    }
+}
+
+// accrues at a fixed rate per block for every open delegation, so tests can delegate,
+// advance the block, and see a non-zero reward to withdraw - a real chain's rate is
+// governed by module params and inflation, which the ensemble has no use modeling
+const REWARD_RATE_BPS: u128 = 100;
+
+// the staking module tracks delegations per (delegator, validator) and the rewards they
+// accrue, plus an optional withdraw address override, mirroring the sdk staking and
+// distribution modules closely enough for contract tests
+#[derive(Default, Clone)]
+struct Staking {
+    delegations: HashMap<(String, String), Coin>,
+    rewards: HashMap<(String, String), Coin>,
+    withdraw_addrs: HashMap<String, String>,
+}
+
+impl Staking {
+    fn delegate(&mut self, delegator: &str, validator: &str, amount: &Coin) -> Result<(), String> {
+        let key = (delegator.to_string(), validator.to_string());
+        let current = self.delegations.get(&key).cloned().unwrap_or_else(|| Coin::new(Uint128::new(0), &amount.denom));
+        let updated = current.amount().checked_add(amount.amount()).map_err(|_| {
+            format!(
+                "delegation overflow: {} delegating {}{} to {} on top of {}",
+                delegator, amount.amount(), amount.denom, validator, current.amount()
+            )
+        })?;
+        self.delegations.insert(key, Coin::new(updated, &amount.denom));
+        Ok(())
+    }
+
+    fn undelegate(&mut self, delegator: &str, validator: &str, amount: &Coin) -> Result<(), String> {
+        let key = (delegator.to_string(), validator.to_string());
+        let current = self
+            .delegations
+            .get(&key)
+            .cloned()
+            .ok_or_else(|| format!("no delegation from {} to {}", delegator, validator))?;
+        let remaining = current.amount().checked_sub(amount.amount()).map_err(|_| {
+            format!("undelegate amount exceeds delegation from {} to {}", delegator, validator)
+        })?;
+        if remaining.u128() == 0 {
+            self.delegations.remove(&key);
+        } else {
+            self.delegations.insert(key, Coin::new(remaining, &amount.denom));
+        }
+        Ok(())
+    }
+
+    fn accrue_rewards(&mut self, blocks: i64) -> Result<(), String> {
+        if blocks <= 0 {
+            return Ok(());
+        }
+        for (key, delegation) in self.delegations.iter() {
+            let reward_amount = delegation
+                .amount()
+                .checked_mul(Uint128::new(REWARD_RATE_BPS))
+                .and_then(|r| r.checked_mul(Uint128::new(blocks as u128)))
+                .and_then(|r| r.checked_div(Uint128::new(10_000)))
+                .map_err(|_| {
+                    format!(
+                        "reward overflow: {}/{} accruing rewards on {} over {} blocks",
+                        key.0, key.1, delegation.amount(), blocks
+                    )
+                })?;
+            if reward_amount.u128() == 0 {
+                continue;
+            }
+            let entry = self
+                .rewards
+                .entry(key.clone())
+                .or_insert_with(|| Coin::new(Uint128::new(0), &delegation.denom));
+            let updated = entry.amount().checked_add(reward_amount).map_err(|_| {
+                format!(
+                    "reward overflow: {}/{} accruing {}{} on top of {}",
+                    key.0, key.1, reward_amount, delegation.denom, entry.amount()
+                )
+            })?;
+            entry.amount = updated.to_string();
+        }
+        Ok(())
+    }
+
+    // clears and returns the accrued reward for (delegator, validator), routed to the
+    // delegator's withdraw address override if one was set
+    fn withdraw_reward(&mut self, delegator: &str, validator: &str) -> Option<(String, Coin)> {
+        let key = (delegator.to_string(), validator.to_string());
+        let reward = self.rewards.remove(&key)?;
+        if reward.amount().u128() == 0 {
+            return None;
+        }
+        let recipient = self
+            .withdraw_addrs
+            .get(delegator)
+            .cloned()
+            .unwrap_or_else(|| delegator.to_string());
+        Some((recipient, reward))
+    }
+}
+
+// a packet dispatched by an IbcMsg::Transfer, escrowed out of `sender`'s balance until
+// the test harness simulates the counterparty chain acknowledging or timing it out
+#[derive(Clone)]
+pub struct IbcPacket {
+    pub id: u64,
+    pub sender: String,
+    pub channel_id: String,
+    pub to_address: String,
+    pub amount: Coin,
+    pub timeout: Option<crate::types::IbcTimeout>,
+}
+
+// models the outbound side of an IBC relayer: packets sit here, escrowed, until a test
+// settles them with `ack_packet` (delivered) or `timeout_packet` (refunded)
+#[derive(Default, Clone)]
+struct IbcState {
+    next_packet_id: u64,
+    pending: HashMap<u64, IbcPacket>,
+}
+
+impl IbcState {
+    fn enqueue(&mut self, sender: &str, msg: &crate::types::IbcTransferMsg) -> u64 {
+        self.next_packet_id += 1;
+        let id = self.next_packet_id;
+        self.pending.insert(
+            id,
+            IbcPacket {
+                id,
+                sender: sender.to_string(),
+                channel_id: msg.channel_id.clone(),
+                to_address: msg.to_address.clone(),
+                amount: msg.amount.clone(),
+                timeout: msg.timeout.clone(),
+            },
+        );
+        id
+    }
+}
+
+// Ensemble wires up a bank, a registry of contract handlers, and a block, so tests can
+// dispatch an execute call and have any CosmosMsg it returns actually carried out,
+// including calls back into other registered contracts.
+pub struct Ensemble {
+    bank: Bank,
+    staking: Staking,
+    ibc: IbcState,
+    contracts: HashMap<String, Box<dyn ContractHandler>>,
+    block: BlockInfo,
+}
+
+impl Default for Ensemble {
+    fn default() -> Self {
+        Ensemble {
+            bank: Bank::default(),
+            staking: Staking::default(),
+            ibc: IbcState::default(),
+            contracts: HashMap::new(),
+            block: BlockInfo {
+                height: 12_345,
+                time: 1_571_797_419,
+                chain_id: "cosmos-testnet-14002".to_string(),
+            },
+        }
+    }
+}
+
+impl Ensemble {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register_contract(&mut self, addr: &str, handler: Box<dyn ContractHandler>) {
+        self.contracts.insert(addr.to_string(), handler);
+    }
+
+    pub fn set_balance(&mut self, addr: &str, balance: &[Coin]) {
+        for coin in balance {
+            self.bank.set_amount(addr, &coin.denom, coin.amount());
+        }
+    }
+
+    pub fn balance(&self, addr: &str) -> Vec<Coin> {
+        self.bank.balance_of(addr)
+    }
+
+    pub fn advance_block(&mut self, height_delta: i64, time_delta: i64) -> Result<(), String> {
+        self.block.height += height_delta;
+        self.block.time += time_delta;
+        self.staking.accrue_rewards(height_delta)
+    }
+
+    pub fn delegation(&self, delegator: &str, validator: &str) -> Option<Coin> {
+        self.staking
+            .delegations
+            .get(&(delegator.to_string(), validator.to_string()))
+            .cloned()
+    }
+
+    pub fn reward(&self, delegator: &str, validator: &str) -> Option<Coin> {
+        self.staking
+            .rewards
+            .get(&(delegator.to_string(), validator.to_string()))
+            .cloned()
+    }
+
+    // packets dispatched by an IbcMsg::Transfer that haven't been settled yet with
+    // `ack_packet` or `timeout_packet`, ordered by the sequence they were sent in
+    pub fn pending_packets(&self) -> Vec<IbcPacket> {
+        let mut packets: Vec<_> = self.ibc.pending.values().cloned().collect();
+        packets.sort_by_key(|p| p.id);
+        packets
+    }
+
+    // simulates the counterparty chain acknowledging receipt: the escrowed coins are
+    // considered delivered there, so they just drop out of the pending queue here
+    pub fn ack_packet(&mut self, packet_id: u64) -> Result<(), String> {
+        self.ibc
+            .pending
+            .remove(&packet_id)
+            .map(|_| ())
+            .ok_or_else(|| format!("no pending ibc packet {}", packet_id))
+    }
+
+    // simulates a relay timeout: the escrowed coins are refunded to the original sender
+    pub fn timeout_packet(&mut self, packet_id: u64) -> Result<(), String> {
+        let packet = self
+            .ibc
+            .pending
+            .remove(&packet_id)
+            .ok_or_else(|| format!("no pending ibc packet {}", packet_id))?;
+        self.bank.add(&packet.sender, std::slice::from_ref(&packet.amount))?;
+        Ok(())
+    }
+
+    // execute dispatches `msg` against the contract registered at `contract_addr`, as if
+    // `sender` called it with `sent_funds` attached, then recursively carries out every
+    // message the response returns. The whole tree commits or rolls back together.
+    pub fn execute(
+        &mut self,
+        contract_addr: &str,
+        sender: &str,
+        sent_funds: &[Coin],
+        msg: String,
+    ) -> Result<Response, String> {
+        let bank_snapshot = self.bank.clone();
+        let staking_snapshot = self.staking.clone();
+        let ibc_snapshot = self.ibc.clone();
+        match self.execute_inner(contract_addr, sender, sent_funds, msg) {
+            Ok(res) => Ok(res),
+            Err(e) => {
+                self.bank = bank_snapshot;
+                self.staking = staking_snapshot;
+                self.ibc = ibc_snapshot;
+                Err(e)
+            }
+        }
+    }
+
+    fn params_for(&self, contract_addr: &str, sender: &str, sent_funds: &[Coin]) -> Params {
+        Params {
+            block: self.block.clone(),
+            message: MessageInfo {
+                signer: sender.to_string(),
+                sent_funds: sent_funds.to_vec(),
+            },
+            contract: ContractInfo {
+                address: contract_addr.to_string(),
+                balance: self.bank.balance_of(contract_addr),
+            },
+        }
+    }
+
+    fn execute_inner(
+        &mut self,
+        contract_addr: &str,
+        sender: &str,
+        sent_funds: &[Coin],
+        msg: String,
+    ) -> Result<Response, String> {
+        if !sent_funds.is_empty() {
+            self.bank.transfer(sender, contract_addr, sent_funds)?;
+        }
+
+        let params = self.params_for(contract_addr, sender, sent_funds);
+        let handler = self
+            .contracts
+            .get(contract_addr)
+            .ok_or_else(|| format!("no contract registered at {}", contract_addr))?;
+        let result = handler.handle(params, msg);
+
+        let mut response = match result.res {
+            Some(ContractResultInner::Ok(res)) => res,
+            Some(ContractResultInner::Err(e)) => return Err(e),
+            None => return Err("contract returned no result".to_string()),
+        };
+
+        // a queue rather than a single pass: messages a `reply` handler returns need to be
+        // dispatched exactly like the ones the original `handle` call returned, so they're
+        // pushed back onto the same queue instead of bubbling up undispatched
+        let mut queue: std::collections::VecDeque<crate::types::Msg> =
+            response.messages.drain(..).collect();
+        while let Some(m) = queue.pop_front() {
+            let id = m.id;
+            let reply_on = ReplyOn::from_i32(m.reply_on)
+                .ok_or_else(|| format!("invalid reply_on discriminant {}", m.reply_on))?;
+            let wants_reply_on_success = matches!(reply_on, ReplyOn::Success | ReplyOn::Always);
+            let wants_reply_on_error = matches!(reply_on, ReplyOn::Error | ReplyOn::Always);
+
+            // a submessage gets its own rollback scope: a caught failure only undoes what
+            // that submessage did, not everything the parent has already committed
+            let bank_snapshot = self.bank.clone();
+            let staking_snapshot = self.staking.clone();
+            let ibc_snapshot = self.ibc.clone();
+            let outcome = match m.msg {
+                Some(CosmosMsg::Send(send)) if send.from_address == contract_addr => self
+                    .bank
+                    .transfer(&send.from_address, &send.to_address, &send.amount)
+                    .map(|_| Response::default()),
+                Some(CosmosMsg::Send(send)) => Err(format!(
+                    "contract {} cannot send funds from {}",
+                    contract_addr, send.from_address
+                )),
+                Some(CosmosMsg::Contract(call)) => {
+                    self.execute_inner(&call.contract_addr, contract_addr, &[], call.msg)
+                }
+                Some(CosmosMsg::Opaque(_)) => {
+                    Err("opaque messages cannot be dispatched by the ensemble".to_string())
+                }
+                Some(CosmosMsg::Staking(staking_msg)) => self
+                    .handle_staking(contract_addr, staking_msg)
+                    .map(|_| Response::default()),
+                Some(CosmosMsg::Distribution(dist_msg)) => self
+                    .handle_distribution(contract_addr, dist_msg)
+                    .map(|_| Response::default()),
+                Some(CosmosMsg::Ibc(ibc_msg)) => self
+                    .handle_ibc(contract_addr, ibc_msg)
+                    .map(|_| Response::default()),
+                None => Ok(Response::default()),
+            };
+
+            match outcome {
+                Ok(sub_response) => {
+                    queue.extend(sub_response.messages.clone());
+                    if wants_reply_on_success {
+                        let reply = Reply {
+                            id: id.unwrap_or(0),
+                            result: Some(SubMsgResult::Ok(SubMsgResponse {
+                                data: sub_response.data.clone(),
+                                events: sub_response.events.clone(),
+                            })),
+                        };
+                        let from_reply = self.invoke_reply(contract_addr, sender, reply)?;
+                        response.data = from_reply.data.or(response.data);
+                        queue.extend(from_reply.messages);
+                    } else if response.data.is_none() {
+                        response.data = sub_response.data;
+                    }
+                }
+                Err(e) => {
+                    if wants_reply_on_error {
+                        self.bank = bank_snapshot;
+                        self.staking = staking_snapshot;
+                        self.ibc = ibc_snapshot;
+                        let reply = Reply {
+                            id: id.unwrap_or(0),
+                            result: Some(SubMsgResult::Err(e)),
+                        };
+                        let from_reply = self.invoke_reply(contract_addr, sender, reply)?;
+                        response.data = from_reply.data.or(response.data);
+                        queue.extend(from_reply.messages);
+                    } else {
+                        return Err(e);
+                    }
+                }
+            }
+        }
+
+        Ok(response)
+    }
+
+    fn invoke_reply(
+        &mut self,
+        contract_addr: &str,
+        sender: &str,
+        reply: Reply,
+    ) -> Result<Response, String> {
+        let params = self.params_for(contract_addr, sender, &[]);
+        let handler = self
+            .contracts
+            .get(contract_addr)
+            .ok_or_else(|| format!("no contract registered at {}", contract_addr))?;
+        let result = handler.reply(params, reply);
+        match result.res {
+            Some(ContractResultInner::Ok(res)) => Ok(res),
+            Some(ContractResultInner::Err(e)) => Err(e),
+            None => Err("contract returned no result from reply".to_string()),
+        }
+    }
+
+    fn handle_staking(&mut self, delegator: &str, msg: StakingMsg) -> Result<(), String> {
+        match msg.action {
+            Some(StakingAction::Delegate(d)) => {
+                self.bank.subtract(delegator, std::slice::from_ref(&d.amount))?;
+                self.staking.delegate(delegator, &d.validator, &d.amount)?;
+                Ok(())
+            }
+            Some(StakingAction::Undelegate(d)) => {
+                self.staking.undelegate(delegator, &d.validator, &d.amount)?;
+                self.bank.add(delegator, std::slice::from_ref(&d.amount))?;
+                Ok(())
+            }
+            Some(StakingAction::Redelegate(r)) => {
+                self.staking.undelegate(delegator, &r.src_validator, &r.amount)?;
+                self.staking.delegate(delegator, &r.dst_validator, &r.amount)?;
+                Ok(())
+            }
+            None => Err("empty staking message".to_string()),
+        }
+    }
+
+    fn handle_distribution(&mut self, delegator: &str, msg: DistributionMsg) -> Result<(), String> {
+        match msg.action {
+            Some(DistributionAction::WithdrawDelegatorReward(w)) => {
+                if let Some((recipient, reward)) = self.staking.withdraw_reward(delegator, &w.validator) {
+                    self.bank.add(&recipient, std::slice::from_ref(&reward))?;
+                }
+                Ok(())
+            }
+            Some(DistributionAction::SetWithdrawAddress(s)) => {
+                self.staking.withdraw_addrs.insert(delegator.to_string(), s.address);
+                Ok(())
+            }
+            None => Err("empty distribution message".to_string()),
+        }
+    }
+
+    fn handle_ibc(&mut self, sender: &str, msg: IbcMsg) -> Result<(), String> {
+        match msg.action {
+            Some(IbcAction::Transfer(transfer)) => {
+                self.bank.subtract(sender, std::slice::from_ref(&transfer.amount))?;
+                self.ibc.enqueue(sender, &transfer);
+                Ok(())
+            }
+            None => Err("empty ibc message".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::{coin, ContractMsg, Msg, Result as ContractResultInner, SendMsg};
+
+    struct PayHandler;
+
+    impl ContractHandler for PayHandler {
+        #[allow(deprecated)]
+        fn handle(&self, params: Params, _msg: String) -> ContractResult {
+            ContractResult {
+                res: Some(ContractResultInner::Ok(Response {
+                    messages: vec![Msg::new(CosmosMsg::Send(SendMsg {
+                        from_address: params.contract.address,
+                        to_address: "recipient".to_string(),
+                        amount: coin("50", "earth"),
+                    }))],
+                    log: Some("paid out".to_string()),
+                    data: None,
+                    events: vec![],
+                })),
+            }
+        }
+    }
+
+    #[test]
+    fn dispatches_send_message_returned_from_contract() {
+        let mut ensemble = Ensemble::new();
+        ensemble.register_contract("payer", Box::new(PayHandler));
+        ensemble.set_balance("payer", &coin("100", "earth"));
+
+        ensemble
+            .execute("payer", "owner", &[], "{}".to_string())
+            .expect("execute should succeed");
+
+        assert_eq!(ensemble.balance("payer"), coin("50", "earth"));
+        assert_eq!(ensemble.balance("recipient"), coin("50", "earth"));
+    }
+
+    struct FailingHandler;
+
+    impl ContractHandler for FailingHandler {
+        fn handle(&self, _params: Params, _msg: String) -> ContractResult {
+            ContractResult {
+                res: Some(ContractResultInner::Err("boom".to_string())),
+            }
+        }
+    }
+
+    struct CallerHandler;
+
+    impl ContractHandler for CallerHandler {
+        fn handle(&self, params: Params, _msg: String) -> ContractResult {
+            ContractResult {
+                res: Some(ContractResultInner::Ok(Response {
+                    messages: vec![
+                        Msg::new(CosmosMsg::Send(SendMsg {
+                            from_address: params.contract.address,
+                            to_address: "recipient".to_string(),
+                            amount: coin("50", "earth"),
+                        })),
+                        Msg::new(CosmosMsg::Contract(ContractMsg {
+                            contract_addr: "failer".to_string(),
+                            msg: "{}".to_string(),
+                        })),
+                    ],
+                    data: None,
+                    ..Default::default()
+                })),
+            }
+        }
+    }
+
+    #[test]
+    fn failed_sub_message_rolls_back_whole_tree() {
+        let mut ensemble = Ensemble::new();
+        ensemble.register_contract("caller", Box::new(CallerHandler));
+        ensemble.register_contract("failer", Box::new(FailingHandler));
+        ensemble.set_balance("caller", &coin("100", "earth"));
+
+        let err = ensemble
+            .execute("caller", "owner", &[], "{}".to_string())
+            .unwrap_err();
+        assert_eq!(err, "boom");
+
+        // the send to "recipient" must have been rolled back along with everything else
+        assert_eq!(ensemble.balance("caller"), coin("100", "earth"));
+        assert_eq!(ensemble.balance("recipient"), vec![]);
+    }
+
+    // dispatches a submessage to "failer", which always errors, but asks for a reply on
+    // error so the failure should be caught rather than aborting the whole transaction
+    struct CatchesErrorHandler;
+
+    impl ContractHandler for CatchesErrorHandler {
+        fn handle(&self, _params: Params, _msg: String) -> ContractResult {
+            ContractResult {
+                res: Some(ContractResultInner::Ok(Response {
+                    messages: vec![Msg::with_reply(
+                        CosmosMsg::Contract(ContractMsg {
+                            contract_addr: "failer".to_string(),
+                            msg: "{}".to_string(),
+                        }),
+                        7,
+                        crate::types::ReplyOn::Error,
+                    )],
+                    data: None,
+                    ..Default::default()
+                })),
+            }
+        }
+
+        fn reply(&self, _params: Params, reply: Reply) -> ContractResult {
+            assert_eq!(reply.id, 7);
+            match reply.result {
+                Some(SubMsgResult::Err(e)) => ContractResult {
+                    res: Some(ContractResultInner::Ok(Response {
+                        messages: vec![],
+                        data: Some(format!("caught: {}", e)),
+                        ..Default::default()
+                    })),
+                },
+                _ => panic!("expected an error reply"),
+            }
+        }
+    }
+
+    #[test]
+    fn reply_on_error_catches_failure_instead_of_aborting() {
+        let mut ensemble = Ensemble::new();
+        ensemble.register_contract("catcher", Box::new(CatchesErrorHandler));
+        ensemble.register_contract("failer", Box::new(FailingHandler));
+
+        let res = ensemble
+            .execute("catcher", "owner", &[], "{}".to_string())
+            .expect("the caught failure should not abort the transaction");
+
+        assert_eq!(res.data, Some("caught: boom".to_string()));
+    }
+
+    // catches the failure like `CatchesErrorHandler`, but its `reply` issues a refund
+    // `Send` of its own - that message must actually be dispatched, not just returned
+    // inertly in the outer `Response`
+    struct RefundsOnErrorHandler;
+
+    impl ContractHandler for RefundsOnErrorHandler {
+        fn handle(&self, params: Params, _msg: String) -> ContractResult {
+            ContractResult {
+                res: Some(ContractResultInner::Ok(Response {
+                    messages: vec![Msg::with_reply(
+                        CosmosMsg::Contract(ContractMsg {
+                            contract_addr: "failer".to_string(),
+                            msg: "{}".to_string(),
+                        }),
+                        7,
+                        crate::types::ReplyOn::Error,
+                    )],
+                    data: Some(params.contract.address),
+                    ..Default::default()
+                })),
+            }
+        }
+
+        fn reply(&self, params: Params, reply: Reply) -> ContractResult {
+            assert_eq!(reply.id, 7);
+            match reply.result {
+                Some(SubMsgResult::Err(_)) => ContractResult {
+                    res: Some(ContractResultInner::Ok(Response {
+                        messages: vec![Msg::new(CosmosMsg::Send(SendMsg {
+                            from_address: params.contract.address,
+                            to_address: "refund_recipient".to_string(),
+                            amount: coin("30", "earth"),
+                        }))],
+                        data: None,
+                        ..Default::default()
+                    })),
+                },
+                _ => panic!("expected an error reply"),
+            }
+        }
+    }
+
+    #[test]
+    fn messages_returned_from_reply_are_actually_dispatched() {
+        let mut ensemble = Ensemble::new();
+        ensemble.register_contract("refunder", Box::new(RefundsOnErrorHandler));
+        ensemble.register_contract("failer", Box::new(FailingHandler));
+        ensemble.set_balance("refunder", &coin("100", "earth"));
+
+        ensemble
+            .execute("refunder", "owner", &[], "{}".to_string())
+            .expect("the caught failure should not abort the transaction");
+
+        assert_eq!(ensemble.balance("refunder"), coin("70", "earth"));
+        assert_eq!(ensemble.balance("refund_recipient"), coin("30", "earth"));
+    }
+
+    struct DelegatingHandler;
+
+    impl ContractHandler for DelegatingHandler {
+        fn handle(&self, _params: Params, _msg: String) -> ContractResult {
+            ContractResult {
+                res: Some(ContractResultInner::Ok(Response {
+                    messages: vec![Msg::new(CosmosMsg::Staking(crate::types::StakingMsg {
+                        action: Some(crate::types::StakingAction::Delegate(
+                            crate::types::DelegateMsg {
+                                validator: "validator1".to_string(),
+                                amount: Coin::new(crate::math::Uint128::new(100), "earth"),
+                            },
+                        )),
+                    }))],
+                    data: None,
+                    ..Default::default()
+                })),
+            }
+        }
+    }
+
+    #[test]
+    fn delegate_then_advance_block_accrues_withdrawable_reward() {
+        let mut ensemble = Ensemble::new();
+        ensemble.register_contract("delegator", Box::new(DelegatingHandler));
+        ensemble.set_balance("delegator", &coin("1000", "earth"));
+
+        ensemble
+            .execute("delegator", "owner", &[], "{}".to_string())
+            .expect("delegate should succeed");
+
+        assert_eq!(
+            ensemble.delegation("delegator", "validator1"),
+            Some(Coin::new(crate::math::Uint128::new(100), "earth"))
+        );
+        assert_eq!(ensemble.balance("delegator"), coin("900", "earth"));
+
+        ensemble.advance_block(10, 60).expect("accruing rewards should succeed");
+        assert_eq!(
+            ensemble.reward("delegator", "validator1"),
+            Some(Coin::new(crate::math::Uint128::new(10), "earth"))
+        );
+
+        ensemble
+            .handle_distribution(
+                "delegator",
+                crate::types::DistributionMsg {
+                    action: Some(crate::types::DistributionAction::WithdrawDelegatorReward(
+                        crate::types::WithdrawDelegatorRewardMsg {
+                            validator: "validator1".to_string(),
+                        },
+                    )),
+                },
+            )
+            .expect("withdraw should succeed");
+
+        assert_eq!(ensemble.balance("delegator"), coin("910", "earth"));
+        assert_eq!(ensemble.reward("delegator", "validator1"), None);
+    }
+
+    struct TransferringHandler;
+
+    impl ContractHandler for TransferringHandler {
+        fn handle(&self, _params: Params, _msg: String) -> ContractResult {
+            ContractResult {
+                res: Some(ContractResultInner::Ok(Response {
+                    messages: vec![Msg::new(CosmosMsg::Ibc(crate::types::IbcMsg {
+                        action: Some(crate::types::IbcAction::Transfer(
+                            crate::types::IbcTransferMsg {
+                                channel_id: "channel-0".to_string(),
+                                to_address: "remote1receiver".to_string(),
+                                amount: Coin::new(crate::math::Uint128::new(250), "earth"),
+                                timeout: Some(crate::types::IbcTimeout::Block(100)),
+                            },
+                        )),
+                    }))],
+                    data: None,
+                    ..Default::default()
+                })),
+            }
+        }
+    }
+
+    #[test]
+    fn ibc_transfer_escrows_funds_until_ack_or_timeout() {
+        let mut ensemble = Ensemble::new();
+        ensemble.register_contract("bridge", Box::new(TransferringHandler));
+        ensemble.set_balance("bridge", &coin("1000", "earth"));
+
+        ensemble
+            .execute("bridge", "owner", &[], "{}".to_string())
+            .expect("transfer should escrow successfully");
+
+        assert_eq!(ensemble.balance("bridge"), coin("750", "earth"));
+        let packets = ensemble.pending_packets();
+        assert_eq!(packets.len(), 1);
+        assert_eq!(packets[0].channel_id, "channel-0");
+        assert_eq!(packets[0].amount, Coin::new(crate::math::Uint128::new(250), "earth"));
+
+        // a timeout refunds the escrowed coins back to the sending contract
+        ensemble
+            .timeout_packet(packets[0].id)
+            .expect("timeout should settle the packet");
+        assert_eq!(ensemble.balance("bridge"), coin("1000", "earth"));
+        assert!(ensemble.pending_packets().is_empty());
+    }
+
+    #[test]
+    fn ibc_transfer_ack_clears_the_packet_without_a_refund() {
+        let mut ensemble = Ensemble::new();
+        ensemble.register_contract("bridge", Box::new(TransferringHandler));
+        ensemble.set_balance("bridge", &coin("1000", "earth"));
+
+        ensemble
+            .execute("bridge", "owner", &[], "{}".to_string())
+            .expect("transfer should escrow successfully");
+
+        let packets = ensemble.pending_packets();
+        ensemble
+            .ack_packet(packets[0].id)
+            .expect("ack should settle the packet");
+
+        // the coins stay escrowed (delivered on the counterparty chain), no refund happens
+        assert_eq!(ensemble.balance("bridge"), coin("750", "earth"));
+        assert!(ensemble.pending_packets().is_empty());
+    }
+}
@@ -0,0 +1,137 @@
+// golden-vector fixtures: a `Params` input and the `ContractResult` a handler is
+// expected to return for it, both stored as plain JSON files on disk. The same pair of
+// files can be checked into a shared corpus and replayed by the Go SDK side, so a
+// mismatch here means the Rust and Go proto encodings have drifted apart.
+//
+// fixtures are tolerant of omission: `Response.log`/`data` are `Option` and `messages`/
+// `events` default to empty when the key is missing entirely (see the `#[serde(default)]`
+// attributes in `types`), so a fixture only needs to spell out the fields it cares about.
+use std::fs;
+use std::path::Path;
+
+use crate::ensemble::ContractHandler;
+use crate::types::{ContractResult, Params};
+
+// loads `params_path` as a `Params` and `expected_path` as the `ContractResult` the
+// handler is expected to produce, runs `msg` through `handler`, and panics with a
+// diff-friendly message if the actual result doesn't structurally match.
+pub fn run_fixture(handler: &dyn ContractHandler, params_path: &Path, msg: String, expected_path: &Path) {
+    let params = load_json::<Params>(params_path);
+    let expected = load_json::<ContractResult>(expected_path);
+
+    let actual = handler.handle(params, msg);
+    assert_eq!(
+        actual,
+        expected,
+        "fixture mismatch: {} did not produce the result in {}",
+        params_path.display(),
+        expected_path.display(),
+    );
+}
+
+fn load_json<T>(path: &Path) -> T
+where
+    T: serde::de::DeserializeOwned,
+{
+    let raw = fs::read_to_string(path)
+        .unwrap_or_else(|e| panic!("failed to read fixture {}: {}", path.display(), e));
+    serde_json::from_str(&raw)
+        .unwrap_or_else(|e| panic!("failed to parse fixture {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct EchoHandler;
+
+    impl ContractHandler for EchoHandler {
+        fn handle(&self, _params: Params, _msg: String) -> ContractResult {
+            ContractResult {
+                res: Some(crate::types::Result::Ok(Default::default())),
+            }
+        }
+    }
+
+    // the expected fixture omits `log`, `data`, and `messages` entirely, exercising the
+    // tolerant decoding that lets a corpus only spell out the fields it cares about
+    #[test]
+    fn run_fixture_accepts_a_result_fixture_with_fields_omitted() {
+        let dir = std::env::temp_dir().join("cosmwasm_fixtures_test");
+        fs::create_dir_all(&dir).expect("create fixture dir");
+
+        let params_path = dir.join("params.json");
+        fs::write(
+            &params_path,
+            r#"{
+                "block": {"height": 1, "time": 2, "chain_id": "test"},
+                "message": {"signer": "alice", "sent_funds": []},
+                "contract": {"address": "cosmos2contract", "balance": []}
+            }"#,
+        )
+        .expect("write params fixture");
+
+        let expected_path = dir.join("expected.json");
+        fs::write(&expected_path, r#"{"ok": {}}"#).expect("write expected fixture");
+
+        run_fixture(&EchoHandler, &params_path, "{}".to_string(), &expected_path);
+    }
+
+    // the golden corpus checked into `fixtures/` at the repo root: real input/output
+    // vectors meant to be replayed by the Go SDK side too, to catch proto-encoding drift
+    struct ReleaseHandler;
+
+    impl ContractHandler for ReleaseHandler {
+        fn handle(&self, params: Params, _msg: String) -> ContractResult {
+            let have = crate::math::amount_of(&params.contract.balance, "earth");
+            let need = crate::math::Uint128::new(50);
+            if have.u128() < need.u128() {
+                return ContractResult {
+                    res: Some(crate::types::Result::Err(format!(
+                        "insufficient funds: {} has {}earth, needs {}earth",
+                        params.contract.address, have, need
+                    ))),
+                };
+            }
+            ContractResult {
+                res: Some(crate::types::Result::Ok(
+                    crate::types::Response {
+                        messages: vec![crate::types::Msg::new(crate::types::CosmosMsg::Send(
+                            crate::types::SendMsg {
+                                from_address: params.contract.address,
+                                to_address: "recipient".to_string(),
+                                amount: crate::types::coin("50", "earth"),
+                            },
+                        ))],
+                        ..crate::types::Response::new()
+                    }
+                    .add_attribute("action", "release"),
+                )),
+            }
+        }
+    }
+
+    fn corpus_path(name: &str) -> std::path::PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures").join(name)
+    }
+
+    #[test]
+    fn release_handler_matches_the_checked_in_send_fixture() {
+        run_fixture(
+            &ReleaseHandler,
+            &corpus_path("send.params.json"),
+            "{}".to_string(),
+            &corpus_path("send.expected.json"),
+        );
+    }
+
+    #[test]
+    fn release_handler_matches_the_checked_in_insufficient_funds_fixture() {
+        run_fixture(
+            &ReleaseHandler,
+            &corpus_path("insufficient_funds.params.json"),
+            "{}".to_string(),
+            &corpus_path("insufficient_funds.expected.json"),
+        );
+    }
+}
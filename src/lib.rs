@@ -0,0 +1,5 @@
+pub mod types;
+pub mod math;
+pub mod ensemble;
+pub mod fixtures;
+mod wire;
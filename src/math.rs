@@ -0,0 +1,181 @@
+use std::fmt;
+use std::str::FromStr;
+
+// Uint128 wraps a u128 and converts to and from a decimal string through Display and
+// FromStr. Coin keeps its proto `string` amount field and converts through `Coin::new`/
+// `Coin::amount`, giving contract code checked arithmetic instead of hand-rolled string
+// parsing. Uint128 itself has no serde Serialize/Deserialize impl.
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Uint128(pub u128);
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum MathError {
+    Overflow,
+    DivideByZero,
+}
+
+impl fmt::Display for MathError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MathError::Overflow => write!(f, "overflow"),
+            MathError::DivideByZero => write!(f, "divide by zero"),
+        }
+    }
+}
+
+impl Uint128 {
+    pub fn new(value: u128) -> Self {
+        Uint128(value)
+    }
+
+    pub fn u128(self) -> u128 {
+        self.0
+    }
+
+    pub fn checked_add(self, other: Uint128) -> Result<Uint128, MathError> {
+        self.0
+            .checked_add(other.0)
+            .map(Uint128)
+            .ok_or(MathError::Overflow)
+    }
+
+    pub fn checked_sub(self, other: Uint128) -> Result<Uint128, MathError> {
+        self.0
+            .checked_sub(other.0)
+            .map(Uint128)
+            .ok_or(MathError::Overflow)
+    }
+
+    pub fn checked_mul(self, other: Uint128) -> Result<Uint128, MathError> {
+        self.0
+            .checked_mul(other.0)
+            .map(Uint128)
+            .ok_or(MathError::Overflow)
+    }
+
+    pub fn checked_div(self, other: Uint128) -> Result<Uint128, MathError> {
+        self.0
+            .checked_div(other.0)
+            .map(Uint128)
+            .ok_or(MathError::DivideByZero)
+    }
+
+    pub fn checked_rem(self, other: Uint128) -> Result<Uint128, MathError> {
+        self.0
+            .checked_rem(other.0)
+            .map(Uint128)
+            .ok_or(MathError::DivideByZero)
+    }
+
+    pub fn checked_pow(self, exp: u32) -> Result<Uint128, MathError> {
+        self.0
+            .checked_pow(exp)
+            .map(Uint128)
+            .ok_or(MathError::Overflow)
+    }
+}
+
+impl fmt::Display for Uint128 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl fmt::Debug for Uint128 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Uint128({})", self.0)
+    }
+}
+
+impl From<u128> for Uint128 {
+    fn from(value: u128) -> Self {
+        Uint128(value)
+    }
+}
+
+impl FromStr for Uint128 {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse::<u128>().map(Uint128)
+    }
+}
+
+use crate::types::Coin;
+
+impl Coin {
+    pub fn new(amount: Uint128, denom: &str) -> Self {
+        Coin {
+            denom: denom.to_string(),
+            amount: amount.to_string(),
+        }
+    }
+
+    pub fn amount(&self) -> Uint128 {
+        self.amount
+            .parse()
+            .unwrap_or_else(|e| panic!("Coin has a malformed amount {:?}: {}", self.amount, e))
+    }
+}
+
+// sums up the amount of `denom` held across a set of coins, e.g. a contract's full balance
+pub fn amount_of(coins: &[Coin], denom: &str) -> Uint128 {
+    coins
+        .iter()
+        .filter(|c| c.denom == denom)
+        .fold(Uint128::default(), |acc, c| {
+            acc.checked_add(c.amount()).unwrap_or(acc)
+        })
+}
+
+// coins is a shortcut constructor for a set of one denomination of coins, using the typed
+// amount instead of a pre-formatted string like `coin()` takes
+pub fn coins(amount: u128, denom: &str) -> Vec<Coin> {
+    vec![Coin::new(Uint128::new(amount), denom)]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn checked_arithmetic_catches_overflow_and_divide_by_zero() {
+        let max = Uint128::new(u128::MAX);
+        assert_eq!(max.checked_add(Uint128::new(1)), Err(MathError::Overflow));
+        assert_eq!(
+            Uint128::new(5).checked_sub(Uint128::new(10)),
+            Err(MathError::Overflow)
+        );
+        assert_eq!(
+            Uint128::new(5).checked_div(Uint128::new(0)),
+            Err(MathError::DivideByZero)
+        );
+        assert_eq!(
+            Uint128::new(10).checked_rem(Uint128::new(3)),
+            Ok(Uint128::new(1))
+        );
+        assert_eq!(
+            Uint128::new(2).checked_pow(10),
+            Ok(Uint128::new(1024))
+        );
+    }
+
+    #[test]
+    fn coin_roundtrips_through_decimal_string() {
+        let c = Coin::new(Uint128::new(1015), "earth");
+        assert_eq!(c.amount, "1015");
+        assert_eq!(c.amount(), Uint128::new(1015));
+    }
+
+    #[test]
+    fn amount_of_sums_matching_denom_only() {
+        let balance = vec![
+            Coin::new(Uint128::new(100), "earth"),
+            Coin::new(Uint128::new(50), "earth"),
+            Coin::new(Uint128::new(7), "moon"),
+        ];
+        assert_eq!(amount_of(&balance, "earth"), Uint128::new(150));
+        assert_eq!(amount_of(&balance, "moon"), Uint128::new(7));
+        assert_eq!(amount_of(&balance, "mars"), Uint128::new(0));
+    }
+}
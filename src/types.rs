@@ -1,6 +1,10 @@
 use prost_derive::{Message};
+use serde::{Deserialize, Serialize};
 
-#[derive(Message, PartialEq, Clone)]
+// every message below also derives Serialize/Deserialize alongside the prost `Message`
+// impl: the same types describe both the host<->wasm binary wire format and the JSON
+// fixtures used for cross-language conformance testing, see `fixtures`
+#[derive(Message, PartialEq, Clone, Debug, Serialize, Deserialize)]
 pub struct Params {
     #[prost(message, required, tag="1")]
     pub block: BlockInfo,
@@ -10,7 +14,7 @@ pub struct Params {
     pub contract: ContractInfo,
 }
 
-#[derive(Message, PartialEq, Clone)]
+#[derive(Message, PartialEq, Clone, Debug, Serialize, Deserialize)]
 pub struct BlockInfo {
     #[prost(int64, tag="1")]
     pub height: i64,
@@ -21,23 +25,25 @@ pub struct BlockInfo {
     pub chain_id: String,
 }
 
-#[derive(Message, PartialEq, Clone)]
+#[derive(Message, PartialEq, Clone, Debug, Serialize, Deserialize)]
 pub struct MessageInfo {
     #[prost(string, tag="1")]
     pub signer: String,
     #[prost(message, repeated, tag="2")]
+    #[serde(default)]
     pub sent_funds: Vec<Coin>,
 }
 
-#[derive(Message, PartialEq, Clone)]
+#[derive(Message, PartialEq, Clone, Debug, Serialize, Deserialize)]
 pub struct ContractInfo {
     #[prost(string, tag="1")]
     pub address: String,
     #[prost(message, repeated, tag="2")]
+    #[serde(default)]
     pub balance: Vec<Coin>,
 }
 
-#[derive(Message, PartialEq, Clone)]
+#[derive(Message, PartialEq, Clone, Debug, Serialize, Deserialize)]
 pub struct Coin {
     #[prost(string, tag="1")]
     pub denom: String,
@@ -45,13 +51,69 @@ pub struct Coin {
     pub amount: String,
 }
 
-#[derive(Message, PartialEq, Clone)]
+#[derive(Message, PartialEq, Clone, Debug, Serialize, Deserialize)]
 pub struct Msg {
-    #[prost(oneof = "CosmosMsg", tags = "1, 2, 3")]
+    #[prost(oneof = "CosmosMsg", tags = "1, 2, 3, 6, 7, 8")]
+    #[serde(flatten)]
     pub msg: Option<CosmosMsg>,
+    // carried back in the Reply (as 0 when unset) when the execution harness re-enters
+    // the dispatching contract's `reply` entrypoint, which `reply_on` decides
+    #[prost(uint64, optional, tag="4")]
+    pub id: Option<u64>,
+    #[prost(enumeration="ReplyOn", tag="5")]
+    #[serde(default, with = "reply_on_serde")]
+    pub reply_on: i32,
 }
 
-#[derive(prost::Oneof, Clone, PartialEq)]
+// `reply_on` stays a raw `i32` on the wire (that's what `#[prost(enumeration)]` expects),
+// but JSON fixtures should read/write the `ReplyOn` variant name, not its discriminant, so
+// this routes through `ReplyOn`'s own Serialize/Deserialize at the JSON boundary instead
+mod reply_on_serde {
+    use super::ReplyOn;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &i32, serializer: S) -> Result<S::Ok, S::Error> {
+        let reply_on = ReplyOn::from_i32(*value).ok_or_else(|| {
+            serde::ser::Error::custom(format!("invalid reply_on discriminant {}", value))
+        })?;
+        reply_on.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i32, D::Error> {
+        Ok(ReplyOn::deserialize(deserializer)? as i32)
+    }
+}
+
+impl Msg {
+    // fire-and-forget, the current behavior: no reply is ever requested
+    pub fn new(msg: CosmosMsg) -> Self {
+        Msg {
+            msg: Some(msg),
+            id: None,
+            reply_on: ReplyOn::Never as i32,
+        }
+    }
+
+    pub fn with_reply(msg: CosmosMsg, id: u64, reply_on: ReplyOn) -> Self {
+        Msg {
+            msg: Some(msg),
+            id: Some(id),
+            reply_on: reply_on as i32,
+        }
+    }
+}
+
+#[derive(prost::Enumeration, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplyOn {
+    Never = 0,
+    Success = 1,
+    Error = 2,
+    Always = 3,
+}
+
+#[derive(prost::Oneof, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum CosmosMsg {
     #[prost(message, tag = "1")]
     Send(SendMsg),
@@ -59,21 +121,31 @@ pub enum CosmosMsg {
     Contract(ContractMsg),
     #[prost(message, tag = "3")]
     Opaque(OpaqueMsg),
+    // tags 4 and 5 are taken by Msg's own `id`/`reply_on` fields (the oneof's variant
+    // tags share the enclosing Msg message's field-number space), so new variants
+    // continue from 6
+    #[prost(message, tag = "6")]
+    Staking(StakingMsg),
+    #[prost(message, tag = "7")]
+    Distribution(DistributionMsg),
+    #[prost(message, tag = "8")]
+    Ibc(IbcMsg),
 }
 
 // this moves tokens in the underlying sdk
-#[derive(Message, PartialEq, Clone)]
+#[derive(Message, PartialEq, Clone, Debug, Serialize, Deserialize)]
 pub struct SendMsg {
     #[prost(string, tag="1")]
     pub from_address: String,
     #[prost(string, tag="2")]
     pub to_address: String,
     #[prost(message, repeated, tag="3")]
+    #[serde(default)]
     pub amount: Vec<Coin>,
 }
 // this dispatches a call to another contract at a known address (with known ABI)
 // msg is the json-encoded HandleMsg struct
-#[derive(Message, PartialEq, Clone)]
+#[derive(Message, PartialEq, Clone, Debug, Serialize, Deserialize)]
 pub struct ContractMsg {
     #[prost(string, tag="1")]
     pub contract_addr: String,
@@ -81,20 +153,168 @@ pub struct ContractMsg {
     pub msg: String,
 }
 // this should never be created here, just passed in from the user and later dispatched
-#[derive(Message, PartialEq, Clone)]
+#[derive(Message, PartialEq, Clone, Debug, Serialize, Deserialize)]
 pub struct OpaqueMsg {
     #[prost(string, tag="1")]
     pub data: String,
 }
 
-#[derive(Message, PartialEq, Clone)]
+// moves coins from the contract's balance into a delegation with the sdk staking module
+#[derive(Message, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct StakingMsg {
+    #[prost(oneof = "StakingAction", tags = "1, 2, 3")]
+    #[serde(flatten)]
+    pub action: Option<StakingAction>,
+}
+
+#[derive(prost::Oneof, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StakingAction {
+    #[prost(message, tag = "1")]
+    Delegate(DelegateMsg),
+    #[prost(message, tag = "2")]
+    Undelegate(UndelegateMsg),
+    #[prost(message, tag = "3")]
+    Redelegate(RedelegateMsg),
+}
+
+#[derive(Message, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct DelegateMsg {
+    #[prost(string, tag="1")]
+    pub validator: String,
+    #[prost(message, required, tag="2")]
+    pub amount: Coin,
+}
+
+#[derive(Message, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct UndelegateMsg {
+    #[prost(string, tag="1")]
+    pub validator: String,
+    #[prost(message, required, tag="2")]
+    pub amount: Coin,
+}
+
+#[derive(Message, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct RedelegateMsg {
+    #[prost(string, tag="1")]
+    pub src_validator: String,
+    #[prost(string, tag="2")]
+    pub dst_validator: String,
+    #[prost(message, required, tag="3")]
+    pub amount: Coin,
+}
+
+// claims staking rewards via the sdk distribution module
+#[derive(Message, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct DistributionMsg {
+    #[prost(oneof = "DistributionAction", tags = "1, 2")]
+    #[serde(flatten)]
+    pub action: Option<DistributionAction>,
+}
+
+#[derive(prost::Oneof, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DistributionAction {
+    #[prost(message, tag = "1")]
+    WithdrawDelegatorReward(WithdrawDelegatorRewardMsg),
+    #[prost(message, tag = "2")]
+    SetWithdrawAddress(SetWithdrawAddressMsg),
+}
+
+#[derive(Message, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct WithdrawDelegatorRewardMsg {
+    #[prost(string, tag="1")]
+    pub validator: String,
+}
+
+#[derive(Message, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct SetWithdrawAddressMsg {
+    #[prost(string, tag="1")]
+    pub address: String,
+}
+
+// an ICS-20 style cross-chain message; only Transfer exists today, mirroring the single
+// IBC application most contracts need
+#[derive(Message, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct IbcMsg {
+    #[prost(oneof = "IbcAction", tags = "1")]
+    #[serde(flatten)]
+    pub action: Option<IbcAction>,
+}
+
+#[derive(prost::Oneof, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IbcAction {
+    #[prost(message, tag = "1")]
+    Transfer(IbcTransferMsg),
+}
+
+// escrows `amount` out of the sending contract's balance and sends it over `channel_id`
+// to `to_address` on the counterparty chain; the transfer is refunded if `timeout` is
+// reached before the counterparty acknowledges it
+#[derive(Message, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct IbcTransferMsg {
+    #[prost(string, tag="1")]
+    pub channel_id: String,
+    #[prost(string, tag="2")]
+    pub to_address: String,
+    #[prost(message, required, tag="3")]
+    pub amount: Coin,
+    #[prost(oneof = "IbcTimeout", tags = "4, 5")]
+    #[serde(flatten)]
+    pub timeout: Option<IbcTimeout>,
+}
+
+#[derive(prost::Oneof, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IbcTimeout {
+    #[prost(uint64, tag = "4")]
+    Block(u64),
+    // nanoseconds since the Unix epoch
+    #[prost(uint64, tag = "5")]
+    Timestamp(u64),
+}
+
+// passed back into the dispatching contract's `reply` entrypoint once a submessage it
+// sent finishes with an outcome its reply_on asks for (Success, Error, or either for
+// Always), so the contract can act on the outcome
+#[derive(Message, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct Reply {
+    #[prost(uint64, tag="1")]
+    pub id: u64,
+    #[prost(oneof = "SubMsgResult", tags = "2, 3")]
+    #[serde(flatten)]
+    pub result: Option<SubMsgResult>,
+}
+
+#[derive(prost::Oneof, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubMsgResult {
+    #[prost(message, tag = "2")]
+    Ok(SubMsgResponse),
+    #[prost(string, tag = "3")]
+    Err(String),
+}
+
+#[derive(Message, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct SubMsgResponse {
+    #[prost(string, optional, tag="1")]
+    pub data: Option<String>,
+    #[prost(message, repeated, tag="2")]
+    #[serde(default)]
+    pub events: Vec<Event>,
+}
+
+#[derive(Message, PartialEq, Clone, Debug, Serialize, Deserialize)]
 pub struct ContractResult {
     #[prost(oneof = "Result", tags = "1, 2")]
+    #[serde(flatten)]
     pub res: Option<Result>,
 }
 
 
-#[derive(prost::Oneof, Clone, PartialEq)]
+#[derive(prost::Oneof, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Result {
     #[prost(message, tag = "1")]
     Ok(Response),
@@ -119,15 +339,100 @@ impl ContractResult {
     }
 }
 
-#[derive(Message, PartialEq, Clone)]
+#[derive(Message, PartialEq, Clone, Debug, Serialize, Deserialize)]
 pub struct Response {
     // let's make the positive case a struct, it contrains Msg: {...}, but also Data, Log, maybe later Events, etc.
     #[prost(message, repeated, tag="1")]
+    #[serde(default)]
     pub messages: Vec<Msg>,
+    #[deprecated(note = "use `events` instead, e.g. via `add_attribute`/`add_event`")]
     #[prost(string, optional, tag="2")]
+    #[serde(default)]
     pub log: Option<String>,
     #[prost(string, optional, tag="3")]
+    #[serde(default)]
     pub data: Option<String>,
+    #[prost(message, repeated, tag="4")]
+    #[serde(default)]
+    pub events: Vec<Event>,
+}
+
+// a single attribute logged within an Event, the building block of the structured
+// replacement for the old flat `log` string, e.g. `action=release`
+#[derive(Message, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct Attribute {
+    #[prost(string, tag="1")]
+    pub key: String,
+    #[prost(string, tag="2")]
+    pub value: String,
+}
+
+// a typed group of attributes, e.g. escrow/cw20 contracts emit one of type "wasm"
+// carrying `action`, `from`, `to`, `amount`
+#[derive(Message, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct Event {
+    #[prost(string, tag="1")]
+    pub ty: String,
+    #[prost(message, repeated, tag="2")]
+    #[serde(default)]
+    pub attributes: Vec<Attribute>,
+}
+
+// the event type every `add_attribute` call appends to by default, and that the
+// deprecated `log` field lowers into when read through `events()`
+const DEFAULT_EVENT_TYPE: &str = "wasm";
+
+#[allow(deprecated)]
+impl Response {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    // appends a key/value pair to the default "wasm" event, creating it if needed -
+    // the common case (escrow/cw20-style `action`, `from`, `to`, `amount` attributes)
+    pub fn add_attribute(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        let attribute = Attribute {
+            key: key.into(),
+            value: value.into(),
+        };
+        match self.events.iter_mut().find(|e| e.ty == DEFAULT_EVENT_TYPE) {
+            Some(event) => event.attributes.push(attribute),
+            None => self.events.push(Event {
+                ty: DEFAULT_EVENT_TYPE.to_string(),
+                attributes: vec![attribute],
+            }),
+        }
+        self
+    }
+
+    pub fn add_event(mut self, event: Event) -> Self {
+        self.events.push(event);
+        self
+    }
+
+    // the typed events to assert against, with the deprecated `log` lowered into a
+    // single default-typed event if it was set instead of (or alongside) `events`
+    pub fn events(&self) -> Vec<Event> {
+        let mut events = self.events.clone();
+        if let Some(log) = &self.log {
+            events.push(Event {
+                ty: DEFAULT_EVENT_TYPE.to_string(),
+                attributes: vec![Attribute {
+                    key: "log".to_string(),
+                    value: log.clone(),
+                }],
+            });
+        }
+        events
+    }
+
+    // lets tests assert "did the response emit an attribute `action=release`" instead
+    // of substring-matching a flat log
+    pub fn has_attribute(&self, key: &str, value: &str) -> bool {
+        self.events()
+            .iter()
+            .any(|e| e.attributes.iter().any(|a| a.key == key && a.value == value))
+    }
 }
 
 // just set signer, sent funds, and balance - rest given defaults
@@ -161,7 +466,7 @@ pub fn coin(amount: &str, denom: &str) -> Vec<Coin> {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::prost::{from_slice, to_vec};
+    use crate::wire::{from_slice, to_vec};
 
     #[test]
     fn can_deser_error_result() {
@@ -174,15 +479,17 @@ mod test {
     }
 
     #[test]
+    #[allow(deprecated)]
     fn can_deser_ok_result() {
         let send = ContractResult{res: Some(Result::Ok(Response {
-            messages: vec![Msg{msg: Some(CosmosMsg::Send(SendMsg {
+            messages: vec![Msg::new(CosmosMsg::Send(SendMsg {
                 from_address: "me".to_string(),
                 to_address: "you".to_string(),
                 amount: coin("1015", "earth"),
-            }))}],
+            }))],
             log: Some("released funds!".to_string()),
             data: None,
+            events: vec![],
         }))};
         let bin = to_vec(&send).expect("encode contract result");
         println!("ok: {}", std::str::from_utf8(&bin).unwrap());
@@ -190,4 +497,26 @@ mod test {
         // need Derive Debug and PartialEq for this, removed to save space
         assert_eq!(send, back);
     }
+
+    #[test]
+    fn has_attribute_finds_key_value_in_added_events() {
+        let res = Response::new()
+            .add_attribute("action", "release")
+            .add_attribute("amount", "1015");
+
+        assert!(res.has_attribute("action", "release"));
+        assert!(res.has_attribute("amount", "1015"));
+        assert!(!res.has_attribute("action", "lock"));
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn deprecated_log_lowers_into_a_default_event() {
+        let res = Response {
+            log: Some("released funds!".to_string()),
+            ..Response::new()
+        };
+
+        assert!(res.has_attribute("log", "released funds!"));
+    }
 }
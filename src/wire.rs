@@ -0,0 +1,14 @@
+// thin wrappers around prost's own `Message::encode`/`decode`, giving the binary wire
+// codec a to_vec/from_slice shape, so callers don't need to reach for `prost::Message`
+// directly.
+use prost::{DecodeError, EncodeError, Message};
+
+pub fn to_vec<M: Message>(msg: &M) -> Result<Vec<u8>, EncodeError> {
+    let mut buf = Vec::with_capacity(msg.encoded_len());
+    msg.encode(&mut buf)?;
+    Ok(buf)
+}
+
+pub fn from_slice<M: Message + Default>(buf: &[u8]) -> Result<M, DecodeError> {
+    M::decode(buf)
+}